@@ -0,0 +1,34 @@
+use std::{fs, path::Path, process::Command};
+
+fn main() {
+    let git_hash = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok());
+    println!(
+        "cargo:rustc-env=GIT_HASH={}",
+        git_hash.as_deref().map(str::trim).unwrap_or("unknown")
+    );
+
+    // the resolved version from the lock file, not the requirement from Cargo.toml
+    let zbus_version = fs::read_to_string("Cargo.lock").ok().and_then(|lock| {
+        lock.split("[[package]]")
+            .find(|package| package.contains("name = \"zbus\"\n"))?
+            .lines()
+            .find_map(|line| line.strip_prefix("version = "))
+            .map(|version| version.trim_matches('"').to_string())
+    });
+    println!(
+        "cargo:rustc-env=ZBUS_VERSION={}",
+        zbus_version.as_deref().unwrap_or("unknown")
+    );
+
+    println!("cargo:rerun-if-changed=Cargo.lock");
+    for git_path in [".git/HEAD", ".git/refs"] {
+        if Path::new(git_path).exists() {
+            println!("cargo:rerun-if-changed={git_path}");
+        }
+    }
+}
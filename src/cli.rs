@@ -0,0 +1,113 @@
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand, ValueEnum};
+
+const VERSION: &str = concat!(
+    env!("CARGO_PKG_VERSION"),
+    " (zbus ",
+    env!("ZBUS_VERSION"),
+    ", git ",
+    env!("GIT_HASH"),
+    ")"
+);
+
+#[derive(Debug, Parser)]
+#[command(version = VERSION)]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// path of the config file, or `-` to read it from stdin. Defaults to the first of
+    /// `$POLYBAR_NOW_PLAYING_CONFIG`, `./now_playing.yaml`,
+    /// `$XDG_CONFIG_HOME/polybar-now-playing/config.yaml` and
+    /// `/etc/polybar-now-playing/config.yaml` that exists
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+
+    /// use the config of a named profile, e.g. `now_playing.laptop.yaml` next to the
+    /// config file. Falls back to the config file if the profile has none
+    #[arg(long)]
+    pub profile: Option<String>,
+
+    /// notify systemd once the first line is printed
+    #[arg(long)]
+    pub systemd: bool,
+
+    /// print plain text with terminal colors instead of polybar markup. This is the
+    /// default when the output is a terminal
+    #[arg(long)]
+    pub plain: bool,
+
+    /// write the shown player to this file on every update as `NP_TITLE=...` lines,
+    /// for other scripts to source
+    #[arg(long, value_name = "PATH")]
+    pub export: Option<PathBuf>,
+
+    /// report the number of D-Bus calls, updates per second and the average metadata
+    /// fetch time to stderr every few seconds
+    #[arg(long)]
+    pub stats: bool,
+
+    /// send a control action to the player and exit: `play`, `pause`, `play-pause`,
+    /// `next`, `previous`, `mute-toggle` or `seek-percent <0-100>`
+    #[arg(long, num_args = 1..=2, value_names = ["ACTION", "VALUE"])]
+    pub control: Option<Vec<String>>,
+
+    /// player name pattern used by `--control`. Defaults to the first player
+    #[arg(long)]
+    pub player: Option<String>,
+
+    /// exact bus name of the player used by `--control`, overriding `--player`
+    #[arg(long)]
+    pub bus_name: Option<String>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// print all MPRIS properties of a player, including the metadata field types
+    Inspect {
+        /// player name pattern, defaults to the first player
+        player: Option<String>,
+    },
+    /// print a single property of a player, failing with empty output when the player
+    /// or the property isn't available
+    Get {
+        property: Property,
+        /// player name pattern, defaults to the player the bar shows with the config
+        #[arg(long)]
+        player: Option<String>,
+    },
+    /// print the desktop entry of a player, usable as its icon name
+    Icon {
+        /// player name pattern, defaults to the player the bar shows with the config
+        player: Option<String>,
+    },
+    /// print the upcoming tracks of a player that provides a tracklist
+    Tracklist {
+        /// player name pattern, defaults to the first player
+        player: Option<String>,
+    },
+    /// check the config against the running players and report likely mistakes
+    Doctor,
+    /// send a command to running instances through their control socket:
+    /// `focus <bus name>`, or `ignore <pattern>` and `unignore <pattern>` to leave out
+    /// players until the instance is restarted
+    Send {
+        /// process id of the instance, defaults to all running instances
+        #[arg(long)]
+        pid: Option<u32>,
+        #[arg(required = true)]
+        command: Vec<String>,
+    },
+}
+
+/// properties printed by the `get` subcommand
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Property {
+    Status,
+    Title,
+    Artist,
+    /// position in seconds
+    Position,
+    Volume,
+}
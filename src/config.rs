@@ -1,9 +1,13 @@
 use std::collections::HashMap;
+use std::ffi::OsString;
 use std::io::Read;
+use std::path::{Path, PathBuf};
 
 use anyhow::Context;
 use serde::{Deserialize, Serialize};
 
+use crate::mpris::PlaybackStatus;
+
 const DEFAULT_CONFIG: &str = include_str!("../default_config.yaml");
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -12,34 +16,588 @@ pub struct Config {
     /// the text will scroll.
     pub message_display_len: usize,
 
-    /// font index of polybar. This value should be 1 higher than the font value specified
-    /// in the polybar config
+    /// width of the whole module including the player prefix, the buttons and
+    /// `module_prefix`/`module_suffix`.
+    /// When set, the text gets whatever width is left and `message_display_len` is ignored
+    #[serde(default)]
+    pub total_width: Option<usize>,
+
+    /// length the text is temporarily expanded to when receiving the signal mapped to
+    /// `expand`, SIGRTMIN+1 by default (`pkill -RTMIN+1 now_playing`). Leave unset to disable expanding
+    #[serde(default)]
+    pub expanded_len: Option<usize>,
+
+    /// time in seconds the text stays expanded
+    #[serde(default = "default_expand_duration")]
+    pub expand_duration: f32,
+
+    /// alignment of text shorter than `message_display_len`: `left`, `center` or `right`
+    #[serde(default)]
+    pub alignment: Alignment,
+
+    /// character filling up text shorter than `message_display_len`
+    #[serde(default = "default_padding_char")]
+    pub padding_char: char,
+
+    /// count characters of ambiguous width, like some symbols and accented letters,
+    /// as two columns as CJK fonts draw them. Used for every width calculation,
+    /// disable it if your font draws them one column wide
+    #[serde(default = "default_true")]
+    pub cjk_width: bool,
+
+    /// font index of polybar as used in `%{T<n>}` tags. This value should be 1 higher than
+    /// the font value specified in the polybar config (`font-0` is `1`), unless
+    /// `font_index_is_polybar_native` is set
     pub font_index: u32,
 
+    /// take `font_index` and `control_font_index` as the N of polybar's `font-N` instead
+    #[serde(default)]
+    pub font_index_is_polybar_native: bool,
+
+    /// font index used for the prefix glyph and the control buttons, following the same
+    /// convention as `font_index`. Without it they use the default font
+    #[serde(default)]
+    pub control_font_index: Option<u32>,
+
+    /// color of the text while the player isn't playing, e.g. `#888888`
+    #[serde(default)]
+    pub dim_when_paused: Option<String>,
+
     /// update speed of the text in seconds
     pub update_delay: f32,
 
+    /// update speed in seconds while no player is available
+    #[serde(default = "default_idle_update_delay")]
+    pub idle_update_delay: f32,
+
+    /// stop updating while DPMS turned the screen off. Needs X11 and `xset`,
+    /// elsewhere the bar keeps updating
+    #[serde(default)]
+    pub pause_when_screen_off: bool,
+
+    /// time in seconds the text stays at its start before scrolling when the track changes
+    #[serde(default)]
+    pub scroll_start_delay: f32,
+
+    /// time in milliseconds a new track has to stay before it counts as a track change,
+    /// so skipping through tracks quickly doesn't restart `scroll_start_delay` every time
+    #[serde(default = "default_change_debounce_ms")]
+    pub change_debounce_ms: u64,
+
+    /// time in milliseconds after a track change during which the previous track
+    /// reappearing is ignored, for players that flip between both tracks while
+    /// crossfading
+    #[serde(default)]
+    pub crossfade_window_ms: u64,
+
+    /// time in milliseconds a message stays at least before it is replaced by a new one
+    #[serde(default)]
+    pub min_message_duration_ms: u64,
+
+    /// time in milliseconds the name of the player is shown after switching to it
+    /// with `next_player`, 0 to switch without it
+    #[serde(default)]
+    pub switch_indicator_ms: u64,
+
+    /// time in milliseconds to wait before the first D-Bus call, for sessions where the
+    /// bar starts before the players are ready. Signals received meanwhile are acted
+    /// on after the delay
+    #[serde(default)]
+    pub startup_delay_ms: u64,
+
+    /// time in milliseconds to wait for a player to answer before keeping the last message
+    #[serde(default = "default_dbus_timeout_ms")]
+    pub dbus_timeout_ms: u64,
+
+    /// glyphs of the control buttons. A button with an empty glyph is hidden
     pub control_chars: ControlChars,
 
-    /// icons to display as prefix for specific players
+    /// replace the player glyph and the play/pause button with a single glyph showing
+    /// the status (`play` while playing, `pause` while paused) that toggles it when clicked
+    #[serde(default)]
+    pub unified_playpause: bool,
+
+    /// control buttons and their order: `previous`, `play_pause`, `next` and `mute`
+    #[serde(default = "default_controls_layout")]
+    pub controls_layout: Vec<ControlButton>,
+
+    /// `controls_layout` for specific players, keyed by player name pattern
+    #[serde(default)]
+    pub player_controls_layouts: HashMap<String, Vec<ControlButton>>,
+
+    /// extra buttons shown after the controls, each running a command when clicked
+    #[serde(default)]
+    pub custom_buttons: Vec<CustomButton>,
+
+    /// disable control buttons the player reports as unavailable
+    /// (e.g. previous at the start of a playlist)
+    #[serde(default)]
+    pub reflect_capabilities: bool,
+
+    /// color of disabled control buttons. Without it they are hidden
+    #[serde(default)]
+    pub disabled_control_color: Option<String>,
+
+    /// icons to display as prefix for specific players. Each pattern is tried against
+    /// the name from the bus name first, then the desktop entry and then the identity
+    /// of the player (see the `inspect` subcommand), falling back to `default`
     pub display_player_prefixes: DisplayPlayerPrefixes,
 
+    /// what the prefix shows: `player` for `display_player_prefixes` or `status` for
+    /// `status_prefixes`
+    #[serde(default)]
+    pub prefix_source: PrefixSource,
+
+    /// prefix glyphs for the playback status, used with `prefix_source: status`
+    #[serde(default)]
+    pub status_prefixes: StatusPrefixes,
+
+    /// text shown for the playback status of players without metadata, keyed by
+    /// `playing`, `paused` or `stopped`. Statuses without a label are shown as
+    /// `Playing`, `Paused` or `Stopped`
+    #[serde(default)]
+    pub status_labels: HashMap<PlaybackStatus, String>,
+
+    /// text between the player prefix and the text, empty for glyphs that bring
+    /// their own spacing
+    #[serde(default = "default_prefix_padding")]
+    pub prefix_padding: String,
+
+    /// player name patterns ordering the player cycle. Players matching an earlier
+    /// pattern come first, players matching none come last
+    #[serde(default)]
+    pub player_priority: Vec<String>,
+
+    /// player name pattern of a player that is always shown while it is playing.
+    /// When it isn't playing, another playing player is picked by `multi_playing_strategy`
+    #[serde(default)]
+    pub primary_player: Option<String>,
+
+    /// when set, a playing player is shown automatically. With several playing players
+    /// `priority` shows the first one by `player_priority`, `first` the one that started
+    /// playing first and `most_recent` the one that started playing last
+    #[serde(default)]
+    pub multi_playing_strategy: Option<MultiPlayingStrategy>,
+
+    /// player name pattern of the player the cycle of `next_player` starts at. The other
+    /// players follow in their `player_priority` order
+    #[serde(default)]
+    pub cycle_anchor: Option<String>,
+
+    /// maximum number of players kept in the cycle after ordering by `player_priority`
+    #[serde(default)]
+    pub max_players: Option<usize>,
+
+    /// drop players from the cycle that aren't playing and have no title,
+    /// like background apps that register on MPRIS without ever playing anything
+    #[serde(default)]
+    pub require_metadata: bool,
+
+    /// look for new and closed players only every this many updates. Players
+    /// appearing or disappearing are still noticed right away through D-Bus,
+    /// but `primary_player` and `multi_playing_strategy` react slower
+    #[serde(default = "default_discovery_interval")]
+    pub discovery_interval: u32,
+
     /// metadata fields based on mpris specification.
     /// See [mpris specification](https://www.freedesktop.org/wiki/Specifications/mpris-spec/metadata/) for more details
+    /// Any other key the player sends works as well, including vendor specific ones.
+    /// Use the `inspect` subcommand to list them
+    /// `xesam:trackNumber` is shown as `#5`, or `#1/5` when the disc number is known
     pub metadata_fields: Vec<String>,
 
     pub metadata_seperator: String,
 
+    /// number of spaces on each side of `metadata_seperator`
+    #[serde(default = "default_separator_spacing")]
+    pub separator_spacing: usize,
+
+    /// number of spaces between the end of scrolling text and its start coming around
+    /// again. One of them is put in front of the text
+    #[serde(default = "default_scroll_gap")]
+    pub scroll_gap: usize,
+
+    /// also scroll text that is exactly as long as `message_display_len`, which is shown
+    /// without scrolling otherwise
+    #[serde(default)]
+    pub scroll_when_exact: bool,
+
+    /// fields appended to the text only when it is too long and scrolls anyway
+    #[serde(default)]
+    pub scroll_extra_fields: Vec<String>,
+
+    /// pairs of fields where the second one is left out when it equals the first one,
+    /// ignoring case, e.g. `[xesam:artist, xesam:albumArtist]`
+    #[serde(default)]
+    pub dedupe_fields: Vec<[String; 2]>,
+
+    /// show `xesam:title` before `xesam:artist` when both are in the fields,
+    /// without reordering `metadata_fields`
+    #[serde(default)]
+    pub title_first: bool,
+
+    /// show all artists of a track, but at most this many followed by `+K more`.
+    /// Without it only the first artist is shown
+    #[serde(default)]
+    pub max_artists: Option<usize>,
+
+    /// cut every metadata field longer than this with an ellipsis, so long fields
+    /// like `xesam:comment` don't take over the whole text. Unlimited when unset
+    #[serde(default)]
+    pub default_field_max_len: Option<usize>,
+
+    /// show the playback rate (e.g. `1.5x`) when it isn't the normal speed
+    #[serde(default)]
+    pub show_rate: bool,
+
+    /// show the track position as `elapsed` (`1:23`), `remaining` (`-3:33`) or
+    /// `both` (`1:23/4:56`). Leave unset to hide it
+    #[serde(default)]
+    pub position_mode: Option<PositionMode>,
+
+    /// show the progress through the track as `text` (`42%`), a single `block`
+    /// character filling up in eighths (`▄`) or a `bar` (`━━━━──────`).
+    /// Leave unset to hide it
+    #[serde(default)]
+    pub progress_style: Option<ProgressStyle>,
+
+    /// length of the progress `bar`
+    #[serde(default = "default_progress_bar_len")]
+    pub progress_bar_len: usize,
+
+    /// rounding of the progress percentage: `floor`, `round` or `ceil`
+    #[serde(default)]
+    pub percentage_round: Rounding,
+
+    /// separators placed between consecutive fields, including their spacing.
+    /// The last one is repeated if there are more fields. Leave empty to use `metadata_seperator`
+    #[serde(default)]
+    pub metadata_separators: Vec<String>,
+
+    /// separators between two specific neighbouring fields, keyed by both field names
+    /// joined with `|`, e.g. `xesam:artist|xesam:title: ' — '`. The domain of
+    /// `show_source_domain` is named `source_domain`. Other boundaries use
+    /// `metadata_separators` or `metadata_seperator`
+    #[serde(default)]
+    pub field_separators: HashMap<String, String>,
+
+    /// groups of metadata fields to rotate through instead of showing all
+    /// `metadata_fields` at once. Leave empty to disable rotation
+    #[serde(default)]
+    pub rotate_fields: Vec<Vec<String>>,
+
+    /// time in seconds each group of `rotate_fields` is shown
+    #[serde(default = "default_rotate_interval")]
+    pub rotate_interval: f32,
+
+    /// text shown when no player is available or the player has no track loaded
+    #[serde(default = "default_no_player_text")]
+    pub no_player_text: String,
+
+    /// text shown while a player is playing but hasn't sent any metadata yet
+    #[serde(default)]
+    pub buffering_text: Option<String>,
+
     /// hide text when no player is available
     pub hide_output: bool,
+
+    /// only print lines that differ from the previous one
+    #[serde(default)]
+    pub skip_unchanged: bool,
+
+    /// with `skip_unchanged`, print the line again after this many updates without a change
+    #[serde(default)]
+    pub force_refresh_interval: Option<u32>,
+
+    /// text put in front of the whole line, outside of the player prefix and the
+    /// scrolling text. Can contain polybar formatting tags
+    #[serde(default)]
+    pub module_prefix: String,
+
+    /// text put behind the whole line, after the control buttons
+    #[serde(default)]
+    pub module_suffix: String,
+
+    /// text printed right at startup until the first update is done
+    #[serde(default)]
+    pub loading_text: Option<String>,
+
+    /// text printed when the script is stopped
+    #[serde(default)]
+    pub shutdown_text: String,
+
+    /// clicking the text toggles between scrolling and showing it cut with an ellipsis.
+    /// Scrolling can also be toggled by sending the signal mapped to `toggle_scroll`
+    #[serde(default)]
+    pub click_to_toggle_scroll: bool,
+
+    /// actions run when receiving a signal. Signals are `SIGUSR1`, `SIGUSR2`, `SIGHUP`,
+    /// `SIGRTMIN` or `SIGRTMIN+<n>`, actions are `next_player`, `toggle_scroll`, `expand`,
+    /// `play_pause`, `next`, `previous`, `reload` and `refresh`. `refresh` updates the bar
+    /// right away, e.g. `pkill -RTMIN now_playing` after a media key changed the track.
+    /// Replaces the default mapping as a whole
+    #[serde(default = "default_signals")]
+    pub signals: HashMap<String, SignalAction>,
+
+    /// what to do when reloading finds a broken config: `keep` the current config or
+    /// `exit`. Everything else is applied right away, only changes to `signals`,
+    /// `startup_delay_ms` and `loading_text` take effect after a restart
+    #[serde(default)]
+    pub reload_on_error: ReloadOnError,
+
+    /// glyph shown in front of the text for live streams (`mpris:length` of 0).
+    /// Live streams without a title show their `xesam:url` instead
+    #[serde(default)]
+    pub live_stream_indicator: Option<char>,
+
+    /// show the domain of the played url (`xesam:url`) in front of the fields for players
+    /// matching `browser_players`
+    #[serde(default)]
+    pub show_source_domain: bool,
+
+    /// player name patterns of browsers for `show_source_domain`
+    #[serde(default = "default_browser_players")]
+    pub browser_players: Vec<String>,
+
+    /// show how long the current track has been playing in real time, e.g. `⏱ 12:34`.
+    /// Unlike the position it doesn't count paused time and isn't affected by seeking
+    #[serde(default)]
+    pub show_session_elapsed: bool,
+
+    /// show the position of the track in the player's tracklist, e.g. `(3/12)`.
+    /// Only works with players providing a tracklist, costs an extra D-Bus call per update
+    #[serde(default)]
+    pub show_tracklist_position: bool,
+
+    /// show the PulseAudio/PipeWire sink the player plays to behind the text, e.g.
+    /// `→ Headphones`. Needs `pactl`, hidden when the sink can't be found
+    #[serde(default)]
+    pub show_sink: bool,
+
+    /// show the prefix glyphs of the other playing players behind the controls,
+    /// clicking one shows that player
+    #[serde(default)]
+    pub show_other_players_glyphs: bool,
+
+    /// show `explicit_marker` behind the text for tracks flagged as explicit
+    #[serde(default)]
+    pub show_explicit: bool,
+
+    /// metadata field flagging explicit tracks. There is no standard field for it,
+    /// check what your player sends with the `inspect` subcommand
+    #[serde(default = "default_explicit_field")]
+    pub explicit_field: String,
+
+    /// marker shown for explicit tracks
+    #[serde(default = "default_explicit_marker")]
+    pub explicit_marker: String,
+
+    /// metadata field with the buffered percentage of streams, shown as ` ⟳45%` while
+    /// it is below 100. There is no standard field for it, check what your player
+    /// sends with the `inspect` subcommand
+    #[serde(default)]
+    pub buffer_field: Option<String>,
+
+    /// collapse whitespace inside metadata values, like tabs and newlines, into single
+    /// spaces and drop control characters
+    #[serde(default = "default_true")]
+    pub normalize_whitespace: bool,
+
+    /// show the rating of the track (`xesam:userRating`) behind the text.
+    /// Hidden for players that don't report a rating
+    #[serde(default)]
+    pub show_rating: bool,
+
+    /// glyphs for the rating from lowest to highest, the rating picks the closest one
+    #[serde(default = "default_rating_glyphs")]
+    pub rating_glyphs: Vec<String>,
+}
+
+fn default_prefix_padding() -> String {
+    " ".into()
+}
+
+fn default_padding_char() -> char {
+    ' '
+}
+
+fn default_separator_spacing() -> usize {
+    1
+}
+
+fn default_scroll_gap() -> usize {
+    3
+}
+
+fn default_expand_duration() -> f32 {
+    5.0
+}
+
+fn default_discovery_interval() -> u32 {
+    1
+}
+
+fn default_idle_update_delay() -> f32 {
+    2.0
+}
+
+fn default_change_debounce_ms() -> u64 {
+    200
+}
+
+fn default_dbus_timeout_ms() -> u64 {
+    1000
+}
+
+fn default_progress_bar_len() -> usize {
+    10
+}
+
+fn default_no_player_text() -> String {
+    "No player available".into()
+}
+
+fn default_rotate_interval() -> f32 {
+    5.0
+}
+
+fn default_browser_players() -> Vec<String> {
+    [
+        "firefox",
+        "chromium",
+        "chrome",
+        "brave",
+        "vivaldi",
+        "opera",
+        "plasma-browser-integration",
+    ]
+    .map(String::from)
+    .to_vec()
+}
+
+fn default_explicit_field() -> String {
+    "xesam:explicit".into()
+}
+
+fn default_explicit_marker() -> String {
+    "🅴".into()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_rating_glyphs() -> Vec<String> {
+    vec!["♡".into(), "♥".into()]
+}
+
+fn default_controls_layout() -> Vec<ControlButton> {
+    vec![
+        ControlButton::Previous,
+        ControlButton::PlayPause,
+        ControlButton::Next,
+        ControlButton::Mute,
+    ]
+}
+
+fn default_signals() -> HashMap<String, SignalAction> {
+    HashMap::from([
+        ("SIGUSR1".into(), SignalAction::NextPlayer),
+        ("SIGUSR2".into(), SignalAction::ToggleScroll),
+        ("SIGRTMIN".into(), SignalAction::Refresh),
+        ("SIGRTMIN+1".into(), SignalAction::Expand),
+        ("SIGHUP".into(), SignalAction::Reload),
+    ])
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Alignment {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressStyle {
+    Text,
+    Block,
+    Bar,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rounding {
+    Floor,
+    #[default]
+    Round,
+    Ceil,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PositionMode {
+    Elapsed,
+    Remaining,
+    Both,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MultiPlayingStrategy {
+    #[default]
+    Priority,
+    First,
+    MostRecent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignalAction {
+    NextPlayer,
+    ToggleScroll,
+    Expand,
+    PlayPause,
+    Next,
+    Previous,
+    Reload,
+    Refresh,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReloadOnError {
+    #[default]
+    Keep,
+    Exit,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ControlChars {
-    pub play: char,
-    pub pause: char,
-    pub previous: char,
-    pub next: char,
+    pub play: String,
+    pub pause: String,
+    pub previous: String,
+    pub next: String,
+    /// mute toggle button, hidden when empty
+    #[serde(default)]
+    pub mute: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ControlButton {
+    Previous,
+    PlayPause,
+    Next,
+    Mute,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CustomButton {
+    pub glyph: String,
+    pub command: String,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -48,12 +606,242 @@ pub struct DisplayPlayerPrefixes {
     pub specific: HashMap<String, char>,
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrefixSource {
+    #[default]
+    Player,
+    Status,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct StatusPrefixes {
+    pub playing: char,
+    pub paused: char,
+    /// also used while no player is available
+    pub stopped: char,
+}
+
+impl Default for StatusPrefixes {
+    fn default() -> Self {
+        StatusPrefixes {
+            playing: '▶',
+            paused: '⏸',
+            stopped: '⏹',
+        }
+    }
+}
+
 impl Config {
     pub fn load(config: impl Read) -> anyhow::Result<Config> {
-        serde_yaml::from_reader(config).context("failed to parse config")
+        let mut config: Config =
+            serde_yaml::from_reader(config).context("failed to parse config")?;
+
+        // an empty list would leave the bar blank
+        if config.metadata_fields.is_empty() {
+            eprintln!("warning: metadata_fields is empty, using xesam:title and xesam:artist");
+            config.metadata_fields = vec!["xesam:title".into(), "xesam:artist".into()];
+        }
+
+        // from here on the font indices are always the ones of %{T<n>} tags
+        if config.font_index_is_polybar_native {
+            config.font_index += 1;
+            config.control_font_index = config.control_font_index.map(|index| index + 1);
+            config.font_index_is_polybar_native = false;
+        }
+        if config.font_index == 0 || config.control_font_index == Some(0) {
+            eprintln!(
+                "warning: font index 0 is written as %{{T0}}, polybar counts fonts in these \
+                 tags from 1 (font-0 is 1), or set font_index_is_polybar_native"
+            );
+        }
+
+        Ok(config)
+    }
+
+    /// signal numbers of the `signals` mapping
+    pub fn signal_actions(&self) -> anyhow::Result<HashMap<i32, SignalAction>> {
+        self.signals
+            .iter()
+            .map(|(name, action)| Ok((signal_number(name)?, *action)))
+            .collect()
     }
 
     pub fn default_str() -> &'static str {
         DEFAULT_CONFIG
     }
 }
+
+/// number of a signal name like `SIGUSR1` or `SIGRTMIN+1`. The `SIG` prefix is optional.
+/// SIGTERM and SIGINT aren't allowed, they always stop the program
+fn signal_number(name: &str) -> anyhow::Result<i32> {
+    let short = name.strip_prefix("SIG").unwrap_or(name);
+    let number = match short {
+        "USR1" => Some(libc::SIGUSR1),
+        "USR2" => Some(libc::SIGUSR2),
+        "HUP" => Some(libc::SIGHUP),
+        "RTMIN" => Some(libc::SIGRTMIN()),
+        _ => short
+            .strip_prefix("RTMIN+")
+            .and_then(|offset| offset.parse::<i32>().ok())
+            .map(|offset| libc::SIGRTMIN() + offset)
+            .filter(|signal| *signal <= libc::SIGRTMAX()),
+    };
+
+    number.with_context(|| format!("unknown or unsupported signal in signals ({name})"))
+}
+
+/// user config path, `$XDG_CONFIG_HOME/polybar-now-playing/config.yaml` with
+/// `XDG_CONFIG_HOME` defaulting to `~/.config`
+fn user_config_path(var: &impl Fn(&str) -> Option<OsString>) -> Option<PathBuf> {
+    let config_home = var("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| Some(PathBuf::from(var("HOME")?).join(".config")))?;
+
+    Some(config_home.join("polybar-now-playing").join("config.yaml"))
+}
+
+/// config file used without `--config`. The first existing one of, in this order:
+///
+/// 1. `$POLYBAR_NOW_PLAYING_CONFIG`
+/// 2. `./now_playing.yaml`
+/// 3. `$XDG_CONFIG_HOME/polybar-now-playing/config.yaml`
+/// 4. `/etc/polybar-now-playing/config.yaml`
+///
+/// When none exists, the user config path is returned so the default config is created there
+pub fn find_config_path() -> anyhow::Result<PathBuf> {
+    config_path_in(|name| std::env::var_os(name), Path::new(""), Path::new("/"))
+}
+
+/// `find_config_path` with the environment variables read by `var`, relative paths
+/// starting at `cwd` and `/etc` inside `root`
+fn config_path_in(
+    var: impl Fn(&str) -> Option<OsString>,
+    cwd: &Path,
+    root: &Path,
+) -> anyhow::Result<PathBuf> {
+    let user_config_path = user_config_path(&var);
+    let candidates = [
+        var("POLYBAR_NOW_PLAYING_CONFIG").map(|path| cwd.join(path)),
+        Some(cwd.join("now_playing.yaml")),
+        user_config_path.clone(),
+        Some(root.join("etc/polybar-now-playing/config.yaml")),
+    ];
+
+    candidates
+        .into_iter()
+        .flatten()
+        .find(|path| path.exists())
+        .or(user_config_path)
+        .context("no config file found and neither XDG_CONFIG_HOME nor HOME is set")
+}
+
+/// config file of a named profile next to the base config, e.g. `now_playing.laptop.yaml`
+/// for `now_playing.yaml`. Profiles are complete configs and replace the base config
+/// instead of being merged into it
+pub fn profile_path(config_path: &Path, profile: &str) -> PathBuf {
+    let stem = config_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    let file_name = match config_path.extension() {
+        Some(extension) => format!("{stem}.{profile}.{}", extension.to_string_lossy()),
+        None => format!("{stem}.{profile}"),
+    };
+
+    config_path.with_file_name(file_name)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /// the default config with the top level keys of `overrides` replaced
+    pub fn load_with(overrides: &str) -> Config {
+        let mut config: serde_yaml::Mapping = serde_yaml::from_str(DEFAULT_CONFIG).unwrap();
+        let overrides: serde_yaml::Mapping = serde_yaml::from_str(overrides).unwrap();
+        config.extend(overrides);
+        Config::load(serde_yaml::to_string(&config).unwrap().as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn empty_metadata_fields_fall_back_to_title_and_artist() {
+        let config = load_with("metadata_fields: []");
+        assert_eq!(config.metadata_fields, ["xesam:title", "xesam:artist"]);
+    }
+
+    /// empty directory for a test, removed again when the test runs next time
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("now_playing-test-{name}"));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn touch(path: &Path) -> PathBuf {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, "").unwrap();
+        path.to_path_buf()
+    }
+
+    #[test]
+    fn config_path_precedence() {
+        let dir = test_dir("config-path");
+        let (cwd, home, root) = (dir.join("cwd"), dir.join("home"), dir.join("root"));
+        std::fs::create_dir_all(&cwd).unwrap();
+        let var = |name: &str| match name {
+            "POLYBAR_NOW_PLAYING_CONFIG" => Some("custom.yaml".into()),
+            "XDG_CONFIG_HOME" => Some(home.join("xdg").into()),
+            "HOME" => Some(home.clone().into()),
+            _ => None,
+        };
+        let find = || config_path_in(var, &cwd, &root).unwrap();
+
+        // nothing exists yet, so the default config goes to the user config path
+        let user = home.join("xdg/polybar-now-playing/config.yaml");
+        assert_eq!(find(), user);
+
+        let system = touch(&root.join("etc/polybar-now-playing/config.yaml"));
+        assert_eq!(find(), system);
+        touch(&user);
+        assert_eq!(find(), user);
+        let local = touch(&cwd.join("now_playing.yaml"));
+        assert_eq!(find(), local);
+        let custom = touch(&cwd.join("custom.yaml"));
+        assert_eq!(find(), custom);
+    }
+
+    #[test]
+    fn config_path_falls_back_to_home() {
+        let dir = test_dir("config-path-home");
+        let var = |name: &str| match name {
+            "XDG_CONFIG_HOME" => Some("".into()),
+            "HOME" => Some(dir.join("home").into()),
+            _ => None,
+        };
+        assert_eq!(
+            config_path_in(var, &dir, &dir).unwrap(),
+            dir.join("home/.config/polybar-now-playing/config.yaml")
+        );
+        assert!(config_path_in(|_| None, &dir, &dir).is_err());
+    }
+
+    #[test]
+    fn polybar_native_font_indices_are_converted() {
+        let config =
+            load_with("font_index: 1\ncontrol_font_index: 2\nfont_index_is_polybar_native: true");
+        assert_eq!(config.font_index, 2);
+        assert_eq!(config.control_font_index, Some(3));
+        assert!(!config.font_index_is_polybar_native);
+        let markup = crate::markup::MarkupBuilder::new()
+            .font(Some(config.font_index))
+            .wrap("song");
+        assert_eq!(markup, "%{T2}song%{T-}");
+
+        let config = load_with("font_index: 1\ncontrol_font_index: 2");
+        assert_eq!(config.font_index, 1);
+        assert_eq!(config.control_font_index, Some(2));
+    }
+}
@@ -0,0 +1,103 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{bail, Context};
+use zbus::{names::OwnedBusName, Connection};
+
+use crate::{mpris, value_to_i64, MprisPlayerProxy};
+
+/// one-shot actions sent to a player via `--control`
+#[derive(Debug)]
+pub enum Control {
+    Play,
+    Pause,
+    PlayPause,
+    Next,
+    Previous,
+    /// sets the volume to 0, or back to the volume saved when muting
+    MuteToggle,
+    /// seek to a percentage of the track length
+    SeekPercent(f64),
+}
+
+impl Control {
+    pub fn parse(args: &[String]) -> anyhow::Result<Control> {
+        match args {
+            [action] if action == "play" => Ok(Control::Play),
+            [action] if action == "pause" => Ok(Control::Pause),
+            [action] if action == "play-pause" => Ok(Control::PlayPause),
+            [action] if action == "next" => Ok(Control::Next),
+            [action] if action == "previous" => Ok(Control::Previous),
+            [action] if action == "mute-toggle" => Ok(Control::MuteToggle),
+            [action, percent] if action == "seek-percent" => {
+                let percent: f64 = percent
+                    .parse()
+                    .with_context(|| format!("invalid percentage ({percent})"))?;
+                if !(0.0..=100.0).contains(&percent) {
+                    bail!("percentage must be between 0 and 100 ({percent})");
+                }
+
+                Ok(Control::SeekPercent(percent))
+            }
+            _ => bail!("unknown control action ({})", args.join(" ")),
+        }
+    }
+
+    pub async fn run(
+        &self,
+        dbus_conn: &Connection,
+        player_name: OwnedBusName,
+    ) -> anyhow::Result<()> {
+        let player = MprisPlayerProxy::builder(dbus_conn)
+            .destination(player_name.clone())?
+            .build()
+            .await?;
+
+        match self {
+            Control::Play => player.play().await?,
+            Control::Pause => player.pause().await?,
+            Control::PlayPause => player.play_pause().await?,
+            Control::Next => player.next().await?,
+            Control::Previous => player.previous().await?,
+            Control::MuteToggle => {
+                // every click is its own process, so the volume is saved in a file
+                let saved_volume_path = saved_volume_path(&player_name)?;
+                let volume = player.volume().await?;
+                if volume > 0.0 {
+                    fs::write(&saved_volume_path, volume.to_string())
+                        .context("failed to save the volume")?;
+                    player.set_volume(0.0).await?;
+                } else {
+                    let saved_volume = fs::read_to_string(&saved_volume_path)
+                        .ok()
+                        .and_then(|volume| volume.trim().parse().ok())
+                        .unwrap_or(1.0);
+                    player.set_volume(saved_volume).await?;
+                    let _ = fs::remove_file(&saved_volume_path);
+                }
+            }
+            Control::SeekPercent(percent) => {
+                let metadata = player.metadata().await?;
+                let length = metadata
+                    .get("mpris:length")
+                    .and_then(|length| value_to_i64(length))
+                    .context("player reports no track length")?;
+                let track_id = mpris::track_id(&metadata).context("player reports no track id")?;
+
+                let position = (length as f64 * percent / 100.0) as i64;
+                player.set_position(&track_id, position).await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// file keeping the volume of a muted player until it is unmuted, in the runtime
+/// directory so it doesn't outlive the session. The shared temp directory is no
+/// fallback, other users could put a symlink at the predictable name there
+fn saved_volume_path(player_name: &OwnedBusName) -> anyhow::Result<PathBuf> {
+    let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .context("XDG_RUNTIME_DIR is not set, mute_toggle needs it to save the volume")?;
+
+    Ok(PathBuf::from(runtime_dir).join(format!("now_playing-volume-{player_name}")))
+}
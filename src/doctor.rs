@@ -0,0 +1,120 @@
+use anyhow::bail;
+use zbus::{fdo::DBusProxy, Connection};
+
+use crate::{
+    config::Config,
+    get_name, matches_player,
+    mpris::{self, PlayerState},
+};
+
+/// prints a report of the config checked against the running players and fails
+/// if anything looks misconfigured
+pub async fn doctor(config: &Config) -> anyhow::Result<()> {
+    let mut problems = 0;
+    let mut problem = |message: String| {
+        println!("problem: {message}");
+        problems += 1;
+    };
+
+    if let Err(err) = config.signal_actions() {
+        problem(format!("{err}"));
+    }
+    // polybar fonts are counted from 1 in %{T} tags
+    for (name, font_index) in [
+        ("font_index", Some(config.font_index)),
+        ("control_font_index", config.control_font_index),
+    ] {
+        if font_index == Some(0) {
+            problem(format!(
+                "{name} is 0, it should be 1 higher than the polybar font-N it refers to"
+            ));
+        }
+    }
+
+    let dbus_conn = Connection::session().await?;
+    let player_names = mpris::list_players(&DBusProxy::new(&dbus_conn).await?).await?;
+    println!("players:");
+    let mut players = Vec::new();
+    for player_name in &player_names {
+        match PlayerState::fetch(&dbus_conn, player_name).await {
+            Ok(player) => {
+                println!("  {} ({})", get_name(player_name.as_str()), player.status);
+                players.push(player);
+            }
+            Err(err) => problem(format!("{player_name} doesn't answer ({err})")),
+        }
+    }
+    if player_names.is_empty() {
+        println!("  none, start a player to check the player specific settings");
+        return finish(problems);
+    }
+
+    let patterns = config
+        .display_player_prefixes
+        .specific
+        .keys()
+        .map(|pattern| ("display_player_prefixes", pattern))
+        .chain(
+            config
+                .player_priority
+                .iter()
+                .map(|pattern| ("player_priority", pattern)),
+        )
+        .chain(
+            config
+                .player_controls_layouts
+                .keys()
+                .map(|pattern| ("player_controls_layouts", pattern)),
+        )
+        .chain(
+            config
+                .primary_player
+                .iter()
+                .map(|pattern| ("primary_player", pattern)),
+        );
+    // prefixes are also looked up by desktop entry and identity
+    let mut root_names = Vec::new();
+    for player_name in &player_names {
+        let (desktop_entry, identity) = mpris::root_names(&dbus_conn, player_name).await;
+        root_names.extend(desktop_entry.into_iter().chain(identity));
+    }
+    for (setting, pattern) in patterns {
+        let matches_root_name = setting == "display_player_prefixes"
+            && root_names.iter().any(|name| matches_player(name, pattern));
+        if !matches_root_name
+            && !player_names
+                .iter()
+                .any(|name| matches_player(get_name(name.as_str()), pattern))
+        {
+            problem(format!(
+                "{setting} pattern {pattern} matches no running player"
+            ));
+        }
+    }
+
+    let fields = config
+        .metadata_fields
+        .iter()
+        .chain(config.rotate_fields.iter().flatten());
+    for field in fields {
+        if !players
+            .iter()
+            .any(|player| player.metadata.contains_key(field))
+        {
+            problem(format!(
+                "no running player sends the metadata field {field}"
+            ));
+        }
+    }
+
+    finish(problems)
+}
+
+fn finish(problems: usize) -> anyhow::Result<()> {
+    if problems > 0 {
+        bail!("found {problems} possible problems");
+    }
+
+    println!("no problems found");
+    Ok(())
+}
@@ -0,0 +1,60 @@
+use std::{fs, path::Path};
+
+use anyhow::Context;
+
+use crate::{mpris::PlayerState, shell_quote, value_to_i64, value_to_string, value_to_strings};
+
+/// writes the state of the shown player as `NP_<KEY>=value` lines that shells can
+/// source. The file is replaced atomically, so readers never see a partial file.
+/// Positions and lengths are in seconds, without a player all values are empty
+pub fn write(path: &Path, player: Option<(&str, &PlayerState)>) -> anyhow::Result<()> {
+    let field = |name: &str| {
+        player
+            .and_then(|(_, player)| player.metadata.get(name))
+            .map(|value| value_to_string(value))
+            .unwrap_or_default()
+    };
+    let artists = player
+        .and_then(|(_, player)| player.metadata.get("xesam:artist"))
+        .map(|artists| value_to_strings(artists).join(", "))
+        .unwrap_or_default();
+    let position = player
+        .and_then(|(_, player)| player.position)
+        .map(|position| (position / 1_000_000).to_string())
+        .unwrap_or_default();
+    let length = player
+        .and_then(|(_, player)| player.metadata.get("mpris:length"))
+        .and_then(|length| value_to_i64(length))
+        .map(|length| (length / 1_000_000).to_string())
+        .unwrap_or_default();
+
+    let vars = [
+        (
+            "NP_PLAYER",
+            player.map(|(name, _)| name.to_string()).unwrap_or_default(),
+        ),
+        (
+            "NP_STATUS",
+            player
+                .map(|(_, player)| player.status.to_string())
+                .unwrap_or_default(),
+        ),
+        ("NP_TITLE", field("xesam:title")),
+        ("NP_ARTIST", artists),
+        ("NP_ALBUM", field("xesam:album")),
+        ("NP_POSITION", position),
+        ("NP_LENGTH", length),
+    ];
+    let content: String = vars
+        .iter()
+        .map(|(key, value)| format!("{key}={}\n", shell_quote(value)))
+        .collect();
+
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    fs::write(&tmp_path, content)
+        .with_context(|| format!("failed to write {}", Path::new(&tmp_path).display()))?;
+    fs::rename(&tmp_path, path).with_context(|| format!("failed to replace {}", path.display()))?;
+
+    Ok(())
+}
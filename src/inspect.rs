@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
+use zbus::{
+    names::OwnedBusName,
+    zvariant::{OwnedValue, Value},
+    Connection,
+};
+
+use crate::{
+    cli::Property,
+    format_duration,
+    mpris::{self, Capabilities, MprisTrackListProxy},
+    value_to_string, value_to_strings, MprisPlayerProxy,
+};
+
+/// formats any value without panicking on types the bar itself doesn't display
+fn format_value(val: &Value) -> String {
+    match val {
+        Value::Array(arr) => format!(
+            "[{}]",
+            arr.get()
+                .iter()
+                .map(format_value)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Value::Structure(structure) => format!(
+            "({})",
+            structure
+                .fields()
+                .iter()
+                .map(format_value)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Value::Dict(dict) => match HashMap::<String, OwnedValue>::try_from(dict.clone()) {
+            Ok(dict) => {
+                let mut entries: Vec<_> = dict.iter().collect();
+                entries.sort_by_key(|(key, _)| key.as_str());
+                let entries: Vec<_> = entries
+                    .into_iter()
+                    .map(|(key, val)| format!("{key:?}: {}", format_value(val)))
+                    .collect();
+                format!("{{{}}}", entries.join(", "))
+            }
+            Err(_) => format!("{dict:?}"),
+        },
+        Value::Str(x) => format!("{:?}", x.as_str()),
+        Value::ObjectPath(x) => x.to_string(),
+        Value::Value(x) => format_value(x),
+        Value::U8(_)
+        | Value::U16(_)
+        | Value::U32(_)
+        | Value::U64(_)
+        | Value::I16(_)
+        | Value::I32(_)
+        | Value::I64(_)
+        | Value::Bool(_)
+        | Value::F64(_) => value_to_string(val),
+        v => format!("{v:?}"),
+    }
+}
+
+/// prints everything the player exposes on the player interface
+pub async fn inspect(dbus_conn: &Connection, player_name: OwnedBusName) -> anyhow::Result<()> {
+    println!("player: {player_name}");
+
+    let player = MprisPlayerProxy::builder(dbus_conn)
+        .destination(player_name)?
+        .build()
+        .await?;
+
+    match player.playback_status().await {
+        Ok(status) => println!("status: {status}"),
+        Err(err) => println!("status: unavailable ({err})"),
+    }
+    match player.position().await {
+        Ok(position) => println!("position: {} ({position})", format_duration(position)),
+        Err(err) => println!("position: unavailable ({err})"),
+    }
+    match player.volume().await {
+        Ok(volume) => println!("volume: {volume}"),
+        Err(err) => println!("volume: unavailable ({err})"),
+    }
+
+    let capabilities = Capabilities::fetch(&player).await;
+    println!("can_go_next: {}", capabilities.can_go_next);
+    println!("can_go_previous: {}", capabilities.can_go_previous);
+    println!("can_play: {}", capabilities.can_play);
+    println!("can_pause: {}", capabilities.can_pause);
+    println!("can_control_volume: {}", capabilities.can_control_volume);
+
+    match player.metadata().await {
+        Ok(metadata) => {
+            println!("metadata:");
+            let mut fields: Vec<_> = metadata.iter().collect();
+            fields.sort_by_key(|(key, _)| key.as_str());
+            for (key, val) in fields {
+                println!("  {key} ({}): {}", val.value_signature(), format_value(val));
+            }
+        }
+        Err(err) => println!("metadata: unavailable ({err})"),
+    }
+
+    Ok(())
+}
+
+/// prints the player's tracklist from the current track on
+pub async fn tracklist(dbus_conn: &Connection, player_name: OwnedBusName) -> anyhow::Result<()> {
+    let player = MprisPlayerProxy::builder(dbus_conn)
+        .destination(player_name.clone())?
+        .build()
+        .await?;
+    let tracklist = MprisTrackListProxy::builder(dbus_conn)
+        .destination(player_name.clone())?
+        .build()
+        .await?;
+
+    let tracks = tracklist
+        .tracks()
+        .await
+        .with_context(|| format!("{player_name} doesn't provide a tracklist"))?;
+    let current = player
+        .metadata()
+        .await
+        .ok()
+        .and_then(|metadata| mpris::track_id(&metadata));
+    let start = tracks
+        .iter()
+        .position(|id| Some(id) == current.as_ref())
+        .unwrap_or(0);
+
+    let upcoming: Vec<_> = tracks[start..].iter().map(|id| id.as_ref()).collect();
+    let metadata = tracklist.get_tracks_metadata(&upcoming).await?;
+    for (i, metadata) in metadata.iter().enumerate() {
+        let field = |name: &str| metadata.get(name).map(|val| value_to_string(val));
+        let title = field("xesam:title").unwrap_or_default();
+        let line = match field("xesam:artist") {
+            Some(artist) => format!("{artist} - {title}"),
+            None => title,
+        };
+        let marker = if i == 0 && current.is_some() {
+            '>'
+        } else {
+            ' '
+        };
+        println!("{marker} {:>3}. {line}", start + i + 1);
+    }
+
+    Ok(())
+}
+
+/// prints a single property of the player for use in scripts
+pub async fn get(
+    dbus_conn: &Connection,
+    player_name: OwnedBusName,
+    property: Property,
+) -> anyhow::Result<()> {
+    let player = MprisPlayerProxy::builder(dbus_conn)
+        .destination(player_name)?
+        .build()
+        .await?;
+
+    let field = |metadata: HashMap<String, OwnedValue>, name: &str| {
+        metadata
+            .get(name)
+            .map(|value| value_to_strings(value).join(", "))
+            .with_context(|| format!("player reports no {name}"))
+    };
+    let value = match property {
+        Property::Status => player.playback_status().await?,
+        Property::Title => field(player.metadata().await?, "xesam:title")?,
+        Property::Artist => field(player.metadata().await?, "xesam:artist")?,
+        Property::Position => (player.position().await? / 1_000_000).to_string(),
+        Property::Volume => player.volume().await?.to_string(),
+    };
+
+    println!("{value}");
+    Ok(())
+}
@@ -0,0 +1,211 @@
+use std::fmt::Display;
+
+/// composes polybar formatting tags around some content. Tags are always opened in the
+/// order actions, foreground color, font and closed in reverse, so every opened tag is
+/// closed and nesting stays valid.
+#[derive(Debug, Default, Clone)]
+pub struct MarkupBuilder {
+    /// mouse button (`None` for the default left click) and unescaped command
+    actions: Vec<(Option<u8>, String)>,
+    foreground: Option<String>,
+    font: Option<u32>,
+}
+
+impl MarkupBuilder {
+    pub fn new() -> MarkupBuilder {
+        MarkupBuilder::default()
+    }
+
+    /// runs `command` on a left click
+    pub fn action(mut self, command: impl Into<String>) -> MarkupBuilder {
+        self.actions.push((None, command.into()));
+        self
+    }
+
+    /// runs `command` when `button` is clicked (1 = left, 2 = middle, 3 = right, ...)
+    pub fn button_action(mut self, button: u8, command: impl Into<String>) -> MarkupBuilder {
+        self.actions.push((Some(button), command.into()));
+        self
+    }
+
+    /// sets the foreground color, does nothing for `None`
+    pub fn foreground(mut self, color: Option<impl Into<String>>) -> MarkupBuilder {
+        if let Some(color) = color {
+            self.foreground = Some(color.into());
+        }
+        self
+    }
+
+    /// sets the font index, does nothing for `None`
+    pub fn font(mut self, font_index: Option<u32>) -> MarkupBuilder {
+        if font_index.is_some() {
+            self.font = font_index;
+        }
+        self
+    }
+
+    pub fn wrap(&self, content: impl Display) -> String {
+        let mut markup = String::new();
+
+        for (button, command) in &self.actions {
+            let button = button.map(|button| button.to_string()).unwrap_or_default();
+            markup += &format!("%{{A{button}:{}:}}", escape_action(command));
+        }
+        if let Some(color) = &self.foreground {
+            markup += &format!("%{{F{color}}}");
+        }
+        if let Some(font_index) = self.font {
+            markup += &format!("%{{T{font_index}}}");
+        }
+
+        markup += &content.to_string();
+
+        if self.font.is_some() {
+            markup += "%{T-}";
+        }
+        if self.foreground.is_some() {
+            markup += "%{F-}";
+        }
+        for _ in &self.actions {
+            markup += "%{A}";
+        }
+
+        markup
+    }
+}
+
+/// escapes a command for use inside a polybar `%{A:command:}` action
+pub fn escape_action(command: impl AsRef<str>) -> String {
+    command.as_ref().replace(':', "\\:")
+}
+
+/// removes all `%{...}` formatting tags, leaving only the visible text
+pub fn strip_markup(text: impl AsRef<str>) -> String {
+    map_tags(text.as_ref(), |_| String::new())
+}
+
+/// turns polybar markup into terminal output: foreground colors become ANSI colors
+/// and all other tags are removed
+pub fn to_terminal(text: impl AsRef<str>) -> String {
+    map_tags(text.as_ref(), |tag| {
+        let Some(color) = tag.strip_prefix('F') else {
+            return String::new();
+        };
+        if color == "-" {
+            return "\x1b[39m".into();
+        }
+
+        // #rrggbb or #aarrggbb, other color formats are dropped
+        let hex = color.trim_start_matches('#');
+        // checking the digits first also keeps the slice on a char boundary
+        let rgb = match hex.len() {
+            6 | 8 if hex.bytes().all(|b| b.is_ascii_hexdigit()) => {
+                u32::from_str_radix(&hex[hex.len() - 6..], 16)
+            }
+            _ => return String::new(),
+        };
+        match rgb {
+            Ok(rgb) => format!(
+                "\x1b[38;2;{};{};{}m",
+                rgb >> 16,
+                (rgb >> 8) & 0xff,
+                rgb & 0xff
+            ),
+            _ => String::new(),
+        }
+    })
+}
+
+/// replaces every `%{...}` tag with what `replace` returns for its content. Commands of
+/// actions (`%{A1:command:}`) are skipped up to their unescaped closing `:`, so they
+/// may contain `}`
+fn map_tags(text: &str, mut replace: impl FnMut(&str) -> String) -> String {
+    let mut mapped = String::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find("%{") {
+        mapped += &rest[..start];
+        let tag = &rest[start + 2..];
+
+        let tag_name_len = tag.strip_prefix('A').map(|name| {
+            1 + name.len() - name.trim_start_matches(|c: char| c.is_ascii_digit()).len()
+        });
+        let mut tag_len = 0;
+        if let Some(command) = tag_name_len.and_then(|len| tag[len..].strip_prefix(':')) {
+            let mut escaped = false;
+            let end = command.char_indices().find(|&(_, c)| {
+                let is_end = c == ':' && !escaped;
+                escaped = c == '\\' && !escaped;
+                is_end
+            });
+            tag_len = match end {
+                Some((end, _)) => tag.len() - command.len() + end + 1,
+                None => tag.len(),
+            };
+        }
+
+        match tag[tag_len..].find('}') {
+            Some(end) => {
+                mapped += &replace(&tag[..tag_len + end]);
+                rest = &tag[tag_len + end + 1..];
+            }
+            None => rest = "",
+        }
+    }
+
+    mapped + rest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_actions_with_escaped_colons() {
+        assert_eq!(strip_markup(r"%{A1:cmd\:x:}play%{A}"), "play");
+        let markup = MarkupBuilder::new()
+            .action("np --control next:now")
+            .wrap("⏭");
+        assert_eq!(markup, r"%{A:np --control next\:now:}⏭%{A}");
+        assert_eq!(strip_markup(markup), "⏭");
+    }
+
+    #[test]
+    fn strips_actions_containing_braces() {
+        assert_eq!(strip_markup("%{A1:echo }:}x%{A}"), "x");
+        assert_eq!(strip_markup("%{A3:sh -c '{ a; }':}y%{A} z"), "y z");
+    }
+
+    #[test]
+    fn strips_nested_tags() {
+        let markup = MarkupBuilder::new()
+            .button_action(3, "np --control next")
+            .action("np --control play-pause")
+            .foreground(Some("#ff0000"))
+            .font(Some(2))
+            .wrap("song");
+        assert_eq!(
+            markup,
+            r"%{A3:np --control next:}%{A:np --control play-pause:}%{F#ff0000}%{T2}song%{T-}%{F-}%{A}%{A}"
+        );
+        assert_eq!(strip_markup(format!("a {markup} b")), "a song b");
+    }
+
+    #[test]
+    fn drops_unterminated_tags() {
+        assert_eq!(strip_markup("abc%{F#fff"), "abc");
+        assert_eq!(strip_markup("abc%{A1:never closed"), "abc");
+    }
+
+    #[test]
+    fn maps_colors_for_terminals() {
+        assert_eq!(
+            to_terminal("%{F#ff8000}red%{F-}"),
+            "\x1b[38;2;255;128;0mred\x1b[39m"
+        );
+        assert_eq!(to_terminal("%{F#80ff8000}a"), "\x1b[38;2;255;128;0ma");
+        assert_eq!(to_terminal("%{Fred}a%{T2}b%{T-}"), "ab");
+        assert_eq!(to_terminal("%{F#ééé}a"), "a");
+        assert_eq!(to_terminal("%{F#aéééa}a"), "a");
+    }
+}
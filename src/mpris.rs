@@ -0,0 +1,401 @@
+use std::{collections::HashMap, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use zbus::{
+    dbus_proxy,
+    fdo::{self, DBusProxy, PropertiesProxy},
+    names::{InterfaceName, OwnedBusName},
+    zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value},
+    Connection,
+};
+
+use crate::{get_name, matches_player, value_to_i64, MPRIS_PREFIX};
+
+pub const MPRIS_PATH: &str = "/org/mpris/MediaPlayer2";
+pub const PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+
+/// track id of players that have nothing loaded
+pub const NO_TRACK: &str = "/org/mpris/MediaPlayer2/TrackList/NoTrack";
+
+// 'org.mpris.MediaPlayer2.Player', 'PlaybackStatus', dbus_interface='org.freedesktop.DBus.Properties'
+#[dbus_proxy(
+    interface = "org.mpris.MediaPlayer2.Player",
+    default_path = "/org/mpris/MediaPlayer2"
+)]
+pub trait MprisPlayer {
+    fn play(&self) -> fdo::Result<()>;
+
+    fn pause(&self) -> fdo::Result<()>;
+
+    fn play_pause(&self) -> fdo::Result<()>;
+
+    fn next(&self) -> fdo::Result<()>;
+
+    fn previous(&self) -> fdo::Result<()>;
+
+    #[dbus_proxy(property)]
+    fn playback_status(&self) -> fdo::Result<String>;
+
+    #[dbus_proxy(property)]
+    fn metadata(&self) -> fdo::Result<HashMap<String, OwnedValue>>;
+
+    #[dbus_proxy(property)]
+    fn rate(&self) -> fdo::Result<f64>;
+
+    #[dbus_proxy(property)]
+    fn position(&self) -> fdo::Result<i64>;
+
+    #[dbus_proxy(property)]
+    fn volume(&self) -> fdo::Result<f64>;
+
+    #[dbus_proxy(property)]
+    fn set_volume(&self, volume: f64) -> fdo::Result<()>;
+
+    #[dbus_proxy(property)]
+    fn can_control(&self) -> fdo::Result<bool>;
+
+    #[dbus_proxy(property)]
+    fn can_go_next(&self) -> fdo::Result<bool>;
+
+    #[dbus_proxy(property)]
+    fn can_go_previous(&self) -> fdo::Result<bool>;
+
+    #[dbus_proxy(property)]
+    fn can_play(&self) -> fdo::Result<bool>;
+
+    #[dbus_proxy(property)]
+    fn can_pause(&self) -> fdo::Result<bool>;
+
+    fn set_position(&self, track_id: &ObjectPath<'_>, position: i64) -> fdo::Result<()>;
+}
+
+#[dbus_proxy(
+    interface = "org.mpris.MediaPlayer2.TrackList",
+    default_path = "/org/mpris/MediaPlayer2"
+)]
+pub trait MprisTrackList {
+    fn get_tracks_metadata(
+        &self,
+        track_ids: &[ObjectPath<'_>],
+    ) -> fdo::Result<Vec<HashMap<String, OwnedValue>>>;
+
+    #[dbus_proxy(property)]
+    fn tracks(&self) -> fdo::Result<Vec<OwnedObjectPath>>;
+}
+
+#[dbus_proxy(
+    interface = "org.mpris.MediaPlayer2",
+    default_path = "/org/mpris/MediaPlayer2"
+)]
+pub trait MprisRoot {
+    #[dbus_proxy(property)]
+    fn desktop_entry(&self) -> fdo::Result<String>;
+
+    #[dbus_proxy(property)]
+    fn identity(&self) -> fdo::Result<String>;
+}
+
+/// MPRIS `PlaybackStatus`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlaybackStatus {
+    Playing,
+    Paused,
+    Stopped,
+}
+
+impl PlaybackStatus {
+    /// parses the value of `PlaybackStatus`. Players sending anything else count
+    /// as stopped, like players not sending it at all
+    pub fn parse(status: &str) -> PlaybackStatus {
+        match status {
+            "Playing" => PlaybackStatus::Playing,
+            "Paused" => PlaybackStatus::Paused,
+            _ => PlaybackStatus::Stopped,
+        }
+    }
+
+    /// the value as MPRIS spells it
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PlaybackStatus::Playing => "Playing",
+            PlaybackStatus::Paused => "Paused",
+            PlaybackStatus::Stopped => "Stopped",
+        }
+    }
+}
+
+impl fmt::Display for PlaybackStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// properties of a player fetched for one update
+pub struct PlayerState {
+    pub status: PlaybackStatus,
+    pub metadata: HashMap<String, OwnedValue>,
+    pub rate: Option<f64>,
+    /// position in microseconds
+    pub position: Option<i64>,
+    pub capabilities: Capabilities,
+    /// see `tracklist_position`, only fetched with `show_tracklist_position`
+    pub tracklist_position: Option<(usize, usize)>,
+    /// audio output of the player, only fetched with `show_sink`
+    pub sink: Option<String>,
+}
+
+/// properties of `PlayerState`, see `PlayerState::fetch`
+const PROPERTIES: [&str; 10] = [
+    "PlaybackStatus",
+    "Metadata",
+    "Rate",
+    "Position",
+    "CanGoNext",
+    "CanGoPrevious",
+    "CanPlay",
+    "CanPause",
+    "CanControl",
+    "Volume",
+];
+
+impl PlayerState {
+    /// fetches all player properties in a single `GetAll` round-trip. Properties
+    /// the player doesn't implement are left empty. Players failing `GetAll` because
+    /// of a single property are asked for each property on its own instead
+    pub async fn fetch(
+        dbus_conn: &Connection,
+        player_name: &OwnedBusName,
+    ) -> anyhow::Result<PlayerState> {
+        let properties = PropertiesProxy::builder(dbus_conn)
+            .destination(player_name)?
+            .path(MPRIS_PATH)?
+            .build()
+            .await?;
+        let interface = InterfaceName::from_static_str(PLAYER_INTERFACE)?;
+        let all = match properties.get_all(interface.clone()).await {
+            Ok(all) => all,
+            Err(err) if is_unsupported(&err) || matches!(err, fdo::Error::Failed(_)) => {
+                let fetches = PROPERTIES.iter().map(|&name| {
+                    let get = properties.get(interface.clone(), name);
+                    async move { Some((name.to_string(), get.await.ok()?)) }
+                });
+                futures::future::join_all(fetches)
+                    .await
+                    .into_iter()
+                    .flatten()
+                    .collect()
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        let get = |name: &str| all.get(name).map(|value| &**value);
+        let get_bool = |name: &str| match get(name) {
+            Some(Value::Bool(x)) => *x,
+            _ => true,
+        };
+
+        Ok(PlayerState {
+            status: match get("PlaybackStatus") {
+                Some(Value::Str(status)) => PlaybackStatus::parse(status),
+                _ => PlaybackStatus::Stopped,
+            },
+            metadata: match get("Metadata") {
+                Some(Value::Dict(dict)) => HashMap::try_from(dict.clone()).unwrap_or_default(),
+                _ => HashMap::new(),
+            },
+            rate: match get("Rate") {
+                Some(Value::F64(rate)) => Some(*rate),
+                _ => None,
+            },
+            position: get("Position").and_then(value_to_i64),
+            capabilities: Capabilities {
+                can_go_next: get_bool("CanGoNext"),
+                can_go_previous: get_bool("CanGoPrevious"),
+                can_play: get_bool("CanPlay"),
+                can_pause: get_bool("CanPause"),
+                can_control_volume: get_bool("CanControl") && get("Volume").is_some(),
+            },
+            tracklist_position: None,
+            sink: None,
+        })
+    }
+}
+
+/// controls supported by a player. Everything counts as supported unless the player
+/// says otherwise
+#[derive(Debug, Clone, Copy)]
+pub struct Capabilities {
+    pub can_go_next: bool,
+    pub can_go_previous: bool,
+    pub can_play: bool,
+    pub can_pause: bool,
+    /// the player has a volume that can be set
+    pub can_control_volume: bool,
+}
+
+impl Default for Capabilities {
+    fn default() -> Capabilities {
+        Capabilities {
+            can_go_next: true,
+            can_go_previous: true,
+            can_play: true,
+            can_pause: true,
+            can_control_volume: true,
+        }
+    }
+}
+
+impl Capabilities {
+    pub async fn fetch(player: &MprisPlayerProxy<'_>) -> Capabilities {
+        Capabilities {
+            can_go_next: player.can_go_next().await.unwrap_or(true),
+            can_go_previous: player.can_go_previous().await.unwrap_or(true),
+            can_play: player.can_play().await.unwrap_or(true),
+            can_pause: player.can_pause().await.unwrap_or(true),
+            can_control_volume: player.can_control().await.unwrap_or(true)
+                && player.volume().await.is_ok(),
+        }
+    }
+}
+
+/// bus names of all available MPRIS players
+pub async fn list_players(dbus_proxy: &DBusProxy<'_>) -> fdo::Result<Vec<OwnedBusName>> {
+    Ok(dbus_proxy
+        .list_names()
+        .await?
+        .into_iter()
+        .filter(|name| matches_player(name.as_str(), MPRIS_PREFIX))
+        .collect())
+}
+
+/// first player whose name matches `pattern`, or the first player without a pattern
+pub async fn find_player(
+    dbus_proxy: &DBusProxy<'_>,
+    pattern: Option<&str>,
+) -> fdo::Result<Option<OwnedBusName>> {
+    Ok(list_players(dbus_proxy).await?.into_iter().find(|name| {
+        pattern.is_none_or(|pattern| matches_player(get_name(name.as_str()), pattern))
+    }))
+}
+
+/// name of the player's .desktop file without the extension, which usually is its icon
+/// name as well. Falls back to the name derived from the bus name
+pub async fn desktop_entry(dbus_conn: &Connection, player_name: &OwnedBusName) -> String {
+    let desktop_entry = async {
+        MprisRootProxy::builder(dbus_conn)
+            .destination(player_name)?
+            .build()
+            .await?
+            .desktop_entry()
+            .await
+            .map_err(zbus::Error::from)
+    };
+
+    match desktop_entry.await {
+        Ok(desktop_entry) if !desktop_entry.is_empty() => desktop_entry,
+        _ => get_name(player_name.as_str()),
+    }
+}
+
+/// desktop entry and identity of the player, `None` for the ones it doesn't set
+pub async fn root_names(
+    dbus_conn: &Connection,
+    player_name: &OwnedBusName,
+) -> (Option<String>, Option<String>) {
+    let root = async {
+        MprisRootProxy::builder(dbus_conn)
+            .destination(player_name)?
+            .build()
+            .await
+    };
+    let Ok(root) = root.await else {
+        return (None, None);
+    };
+
+    let desktop_entry = root.desktop_entry().await.ok();
+    let identity = root.identity().await.ok();
+    (
+        desktop_entry.filter(|name| !name.is_empty()),
+        identity.filter(|name| !name.is_empty()),
+    )
+}
+
+/// 1-based position of the track in the player's tracklist and the tracklist length.
+/// `None` for players without the TrackList interface or tracks not in the list
+pub async fn tracklist_position(
+    dbus_conn: &Connection,
+    player_name: &OwnedBusName,
+    track_id: &OwnedObjectPath,
+) -> Option<(usize, usize)> {
+    let tracks = MprisTrackListProxy::builder(dbus_conn)
+        .destination(player_name)
+        .ok()?
+        .build()
+        .await
+        .ok()?
+        .tracks()
+        .await
+        .ok()?;
+
+    let position = tracks.iter().position(|id| id == track_id)?;
+    Some((position + 1, tracks.len()))
+}
+
+/// `mpris:trackid` of the metadata. Some players send it as a string instead of an object path
+pub fn track_id(metadata: &HashMap<String, OwnedValue>) -> Option<OwnedObjectPath> {
+    match &**metadata.get("mpris:trackid")? {
+        Value::ObjectPath(id) => Some(id.to_owned().into()),
+        Value::Str(id) => ObjectPath::try_from(id.as_str())
+            .ok()
+            .map(|id| id.into_owned().into()),
+        _ => None,
+    }
+}
+
+/// whether the error means the player doesn't implement something, as opposed to
+/// the bus or the player failing
+pub fn is_unsupported(err: &fdo::Error) -> bool {
+    matches!(
+        err,
+        fdo::Error::NotSupported(_)
+            | fdo::Error::InvalidArgs(_)
+            | fdo::Error::UnknownMethod(_)
+            | fdo::Error::UnknownInterface(_)
+            | fdo::Error::UnknownProperty(_)
+    )
+}
+
+/// whether the error means the player left the bus, e.g. it quit after the
+/// players were last looked up
+pub fn is_gone(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref(),
+            Some(fdo::Error::ServiceUnknown(_) | fdo::Error::NameHasNoOwner(_))
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unexpected_statuses_count_as_stopped() {
+        assert_eq!(PlaybackStatus::parse("Playing"), PlaybackStatus::Playing);
+        assert_eq!(PlaybackStatus::parse("Paused"), PlaybackStatus::Paused);
+        assert_eq!(PlaybackStatus::parse("Stopped"), PlaybackStatus::Stopped);
+        for status in ["playing", "PAUSED", " Playing", "Buffering", ""] {
+            assert_eq!(PlaybackStatus::parse(status), PlaybackStatus::Stopped);
+        }
+        for status in [
+            PlaybackStatus::Playing,
+            PlaybackStatus::Paused,
+            PlaybackStatus::Stopped,
+        ] {
+            assert_eq!(PlaybackStatus::parse(status.as_str()), status);
+        }
+    }
+}
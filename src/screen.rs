@@ -0,0 +1,31 @@
+use std::time::Duration;
+
+use tokio::process::Command;
+
+/// time xset gets to answer, e.g. a stuck X server must not freeze the bar
+const XSET_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// whether DPMS turned the X11 display off, read from `xset q`. Without X11,
+/// like on Wayland, or without `xset` answering in time the screen always counts as on
+pub async fn screen_off() -> bool {
+    if std::env::var_os("DISPLAY").is_none() {
+        return false;
+    }
+
+    let output = Command::new("xset")
+        .arg("q")
+        .env("LC_ALL", "C")
+        .kill_on_drop(true)
+        .output();
+    let Ok(Ok(output)) = tokio::time::timeout(XSET_TIMEOUT, output).await else {
+        return false;
+    };
+
+    output.status.success()
+        && String::from_utf8_lossy(&output.stdout).lines().any(|line| {
+            let line = line.trim();
+            line == "Monitor is Off"
+                || line == "Monitor is in Standby"
+                || line == "Monitor is in Suspend"
+        })
+}
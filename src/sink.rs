@@ -0,0 +1,76 @@
+use std::{collections::HashMap, time::Duration};
+
+use tokio::process::Command;
+
+/// time pactl gets to answer, a hanging sound server must not freeze the bar
+const PACTL_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// output of a `pactl list` command, `None` when pactl is missing, fails or
+/// doesn't finish within `PACTL_TIMEOUT`
+async fn pactl_list(object: &str) -> Option<String> {
+    let output = Command::new("pactl")
+        .args(["list", object])
+        .env("LC_ALL", "C")
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(PACTL_TIMEOUT, output)
+        .await
+        .ok()?
+        .ok()?;
+
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// splits `pactl list` output into its objects, keyed by their index
+/// (`Sink #3` or `Sink Input #42`)
+fn objects(list: &str) -> HashMap<u32, Vec<&str>> {
+    let mut objects = HashMap::new();
+    let mut current = None;
+
+    for line in list.lines() {
+        if !line.starts_with(char::is_whitespace) {
+            current = line
+                .rsplit_once('#')
+                .and_then(|(_, index)| index.trim().parse().ok());
+        } else if let Some(index) = current {
+            objects
+                .entry(index)
+                .or_insert_with(Vec::new)
+                .push(line.trim());
+        }
+    }
+
+    objects
+}
+
+/// value of a `key: value` or `key = "value"` line of an object
+fn field<'a>(object: &[&'a str], key: &str) -> Option<&'a str> {
+    object.iter().find_map(|line| {
+        let value = line.strip_prefix(key)?;
+        let value = value
+            .strip_prefix(':')
+            .or_else(|| value.trim_start().strip_prefix('='))?;
+        Some(value.trim().trim_matches('"'))
+    })
+}
+
+/// description of the PulseAudio/PipeWire sink the process plays to, `None` when it
+/// isn't playing or `pactl` isn't available
+pub async fn sink_of_process(pid: u32) -> Option<String> {
+    let sink_inputs = pactl_list("sink-inputs").await?;
+    let sink = objects(&sink_inputs).into_values().find_map(|input| {
+        (field(&input, "application.process.id")? == pid.to_string())
+            .then(|| field(&input, "Sink")?.parse::<u32>().ok())
+            .flatten()
+    })?;
+
+    let sinks = pactl_list("sinks").await?;
+    let objects = objects(&sinks);
+    let sink = objects.get(&sink)?;
+    field(sink, "Description")
+        .or_else(|| field(sink, "Name"))
+        .map(str::to_string)
+}
@@ -0,0 +1,171 @@
+use std::{fs, io::ErrorKind, path::PathBuf};
+
+use anyhow::{bail, Context};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{UnixListener, UnixStream},
+    sync::mpsc,
+};
+
+/// commands sent to a running instance through its control socket with `send`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SocketCommand {
+    /// show the player with this bus name
+    Focus(String),
+    /// leave out players matching the pattern until `unignore` or a restart
+    Ignore(String),
+    Unignore(String),
+}
+
+impl SocketCommand {
+    pub fn parse(args: &[impl AsRef<str>]) -> anyhow::Result<SocketCommand> {
+        let args: Vec<&str> = args.iter().map(AsRef::as_ref).collect();
+        match args[..] {
+            ["focus", bus_name] => Ok(SocketCommand::Focus(bus_name.into())),
+            ["ignore", pattern] => Ok(SocketCommand::Ignore(pattern.into())),
+            ["unignore", pattern] => Ok(SocketCommand::Unignore(pattern.into())),
+            _ => bail!("unknown command ({})", args.join(" ")),
+        }
+    }
+}
+
+/// the private runtime directory of the user. There is no fallback to the shared
+/// temp directory, where other users could take over the predictable socket names
+fn runtime_dir() -> anyhow::Result<PathBuf> {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .context("XDG_RUNTIME_DIR is not set, control sockets need it")
+}
+
+/// socket of the instance with the given process id
+fn socket_path(pid: u32) -> anyhow::Result<PathBuf> {
+    Ok(runtime_dir()?.join(format!("now_playing-{pid}.sock")))
+}
+
+/// sockets of all running instances
+fn socket_paths() -> anyhow::Result<Vec<PathBuf>> {
+    let Ok(entries) = fs::read_dir(runtime_dir()?) else {
+        return Ok(Vec::new());
+    };
+
+    Ok(entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("now_playing-") && name.ends_with(".sock"))
+        })
+        .collect())
+}
+
+/// socket receiving commands for this instance, one line per connection.
+/// The socket file is removed again when this is dropped
+pub struct ControlSocket {
+    path: Option<PathBuf>,
+    commands: mpsc::UnboundedReceiver<SocketCommand>,
+}
+
+impl ControlSocket {
+    /// listens on the socket of this process. Without a socket the program still
+    /// works, just without `send`, so failing to create it is only a warning
+    pub fn bind() -> ControlSocket {
+        let (sender, commands) = mpsc::unbounded_channel();
+        let path = match socket_path(std::process::id()) {
+            Ok(path) => path,
+            Err(err) => {
+                eprintln!("warning: no control socket ({err})");
+                return ControlSocket {
+                    path: None,
+                    commands,
+                };
+            }
+        };
+        let listener = match UnixListener::bind(&path) {
+            Ok(listener) => listener,
+            Err(err) => {
+                eprintln!(
+                    "warning: failed to create control socket {} ({err})",
+                    path.display()
+                );
+                return ControlSocket {
+                    path: None,
+                    commands,
+                };
+            }
+        };
+
+        // connections are read in their own task, so a slow client can't hold up the bar
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let sender = sender.clone();
+                tokio::spawn(async move {
+                    let mut line = String::new();
+                    if BufReader::new(stream).read_line(&mut line).await.is_err() {
+                        return;
+                    }
+                    let args: Vec<&str> = line.split_whitespace().collect();
+                    match SocketCommand::parse(&args) {
+                        Ok(command) => _ = sender.send(command),
+                        Err(err) => eprintln!("warning: control socket: {err}"),
+                    }
+                });
+            }
+        });
+
+        ControlSocket {
+            path: Some(path),
+            commands,
+        }
+    }
+
+    /// next received command, `None` if there is no socket
+    pub async fn recv(&mut self) -> Option<SocketCommand> {
+        self.commands.recv().await
+    }
+}
+
+impl Drop for ControlSocket {
+    fn drop(&mut self) {
+        if let Some(path) = &self.path {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// sends a command to the instance with the given process id, or to all running
+/// instances. Sockets left behind by instances that didn't exit cleanly are removed
+pub async fn send(pid: Option<u32>, args: &[String]) -> anyhow::Result<()> {
+    // checked here as well, so typos fail in the terminal instead of the bar's log
+    SocketCommand::parse(args)?;
+    let line = format!("{}\n", args.join(" "));
+
+    let paths = match pid {
+        Some(pid) => vec![socket_path(pid)?],
+        None => socket_paths()?,
+    };
+    let mut sent = 0;
+    for path in paths {
+        match UnixStream::connect(&path).await {
+            Ok(mut stream) => {
+                stream
+                    .write_all(line.as_bytes())
+                    .await
+                    .with_context(|| format!("failed to send to {}", path.display()))?;
+                sent += 1;
+            }
+            Err(err) if pid.is_none() && err.kind() == ErrorKind::ConnectionRefused => {
+                let _ = fs::remove_file(&path);
+            }
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to connect to {}", path.display()))
+            }
+        }
+    }
+
+    if sent == 0 {
+        bail!("no running instance found");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use std::time::{Duration, Instant};
+
+/// time between two `--stats` reports
+const REPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// counters for `--stats`, reported to stderr and reset every `REPORT_INTERVAL`
+#[derive(Debug)]
+pub struct Stats {
+    dbus_calls: u32,
+    fetches: u32,
+    fetch_time: Duration,
+    ticks: u32,
+    since: Instant,
+}
+
+impl Stats {
+    pub fn new() -> Stats {
+        Stats {
+            dbus_calls: 0,
+            fetches: 0,
+            fetch_time: Duration::ZERO,
+            ticks: 0,
+            since: Instant::now(),
+        }
+    }
+
+    pub fn dbus_calls(&mut self, calls: usize) {
+        self.dbus_calls += calls as u32;
+    }
+
+    /// a metadata fetch of the shown player, which is one D-Bus call
+    pub fn fetch(&mut self, time: Duration) {
+        self.dbus_calls += 1;
+        self.fetches += 1;
+        self.fetch_time += time;
+    }
+
+    /// counts an update and reports once `REPORT_INTERVAL` has passed
+    pub fn tick(&mut self) {
+        self.ticks += 1;
+
+        let elapsed = self.since.elapsed();
+        if elapsed < REPORT_INTERVAL {
+            return;
+        }
+
+        let average_fetch = self
+            .fetch_time
+            .checked_div(self.fetches)
+            .unwrap_or_default();
+        eprintln!(
+            "stats: {} D-Bus calls, {:.1} ticks/s, average fetch {average_fetch:?}",
+            self.dbus_calls,
+            self.ticks as f32 / elapsed.as_secs_f32()
+        );
+        *self = Stats::new();
+    }
+}